@@ -0,0 +1,165 @@
+// Event filtering and aggregation: turns a raw event dump into an
+// actionable triage list.
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::core::v1::Event;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Which events to keep. Kubernetes only ever sets `type` to `Normal` or
+/// `Warning`, so there's no finer granularity to offer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventLevel {
+    All,
+    Warning,
+}
+
+impl FromStr for EventLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(EventLevel::All),
+            "warning" => Ok(EventLevel::Warning),
+            other => Err(format!("unknown events level '{}' (expected 'all' or 'warning')", other)),
+        }
+    }
+}
+
+/// A group of duplicate events collapsed to a single triage line.
+pub struct AggregatedEvent {
+    pub involved_object: String,
+    pub reason: String,
+    pub message: String,
+    pub count: i32,
+    pub last_timestamp: Option<DateTime<Utc>>,
+}
+
+/// Filter raw events down to `level`, drop anything older than `since`,
+/// and group duplicates by (involved object, reason, message) so a
+/// flapping pod shows up as one line with an occurrence count.
+pub fn filter_and_aggregate(
+    events: Vec<Event>,
+    level: EventLevel,
+    since: Option<Duration>,
+) -> Vec<AggregatedEvent> {
+    let cutoff = since.and_then(|d| {
+        chrono::Duration::from_std(d)
+            .ok()
+            .map(|d| Utc::now() - d)
+    });
+
+    let mut groups: HashMap<(String, String, String), AggregatedEvent> = HashMap::new();
+
+    for event in events {
+        if level == EventLevel::Warning && event.type_.as_deref() != Some("Warning") {
+            continue;
+        }
+
+        let last_timestamp = event.last_timestamp.as_ref().map(|t| t.0);
+        if let Some(cutoff) = cutoff {
+            if let Some(ts) = last_timestamp {
+                if ts < cutoff {
+                    continue;
+                }
+            }
+        }
+
+        let involved_object = format!(
+            "{}/{}",
+            event.involved_object.kind.as_deref().unwrap_or("Unknown"),
+            event.involved_object.name.as_deref().unwrap_or("unknown")
+        );
+        let reason = event.reason.clone().unwrap_or_default();
+        let message = event.message.clone().unwrap_or_default();
+        let count = event.count.unwrap_or(1);
+
+        let key = (involved_object.clone(), reason.clone(), message.clone());
+        groups
+            .entry(key)
+            .and_modify(|agg| {
+                agg.count += count;
+                if last_timestamp > agg.last_timestamp {
+                    agg.last_timestamp = last_timestamp;
+                }
+            })
+            .or_insert(AggregatedEvent {
+                involved_object,
+                reason,
+                message,
+                count,
+                last_timestamp,
+            });
+    }
+
+    let mut aggregated: Vec<AggregatedEvent> = groups.into_values().collect();
+    aggregated.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| b.last_timestamp.cmp(&a.last_timestamp))
+    });
+    aggregated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::ObjectReference;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+
+    fn event(kind: &str, name: &str, type_: &str, reason: &str, message: &str, count: i32) -> Event {
+        Event {
+            involved_object: ObjectReference {
+                kind: Some(kind.to_string()),
+                name: Some(name.to_string()),
+                ..Default::default()
+            },
+            type_: Some(type_.to_string()),
+            reason: Some(reason.to_string()),
+            message: Some(message.to_string()),
+            count: Some(count),
+            last_timestamp: Some(Time(Utc::now())),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn collapses_duplicate_events_into_one_with_summed_count() {
+        let events = vec![
+            event("Pod", "flapper", "Warning", "BackOff", "crash looping", 3),
+            event("Pod", "flapper", "Warning", "BackOff", "crash looping", 2),
+        ];
+
+        let aggregated = filter_and_aggregate(events, EventLevel::All, None);
+
+        assert_eq!(aggregated.len(), 1);
+        assert_eq!(aggregated[0].count, 5);
+        assert_eq!(aggregated[0].involved_object, "Pod/flapper");
+    }
+
+    #[test]
+    fn warning_level_drops_normal_events() {
+        let events = vec![
+            event("Pod", "a", "Normal", "Scheduled", "scheduled", 1),
+            event("Pod", "b", "Warning", "Failed", "failed", 1),
+        ];
+
+        let aggregated = filter_and_aggregate(events, EventLevel::Warning, None);
+
+        assert_eq!(aggregated.len(), 1);
+        assert_eq!(aggregated[0].reason, "Failed");
+    }
+
+    #[test]
+    fn sorts_by_count_descending() {
+        let events = vec![
+            event("Pod", "quiet", "Warning", "Failed", "once", 1),
+            event("Pod", "loud", "Warning", "BackOff", "lots", 9),
+        ];
+
+        let aggregated = filter_and_aggregate(events, EventLevel::All, None);
+
+        assert_eq!(aggregated[0].involved_object, "Pod/loud");
+        assert_eq!(aggregated[1].involved_object, "Pod/quiet");
+    }
+}
@@ -0,0 +1,6 @@
+// Process exit codes. Distinct codes let scripts tell "the cluster was
+// unreachable" apart from "the cluster answered but a check found a
+// problem".
+pub const OK: i32 = 0;
+pub const CHECKS_FAILED: i32 = 1;
+pub const CLUSTER_UNREACHABLE: i32 = 2;
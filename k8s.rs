@@ -0,0 +1,182 @@
+// Native Kubernetes client layer, replacing the old kubectl shell-outs.
+use k8s_openapi::api::core::v1::{Event, Node, Pod};
+use kube::api::{Api, ListParams};
+use kube::config::{KubeConfigOptions, Kubeconfig, KubeconfigError};
+use kube::{Client, Config};
+use std::fmt;
+use std::path::Path;
+
+/// Identifies which cluster a report was run against, for the report
+/// header.
+pub struct ClusterInfo {
+    pub context: String,
+    pub server: String,
+}
+
+/// Everything that can go wrong while turning a kubeconfig into a
+/// connected `Client`: reading/parsing the file, or the client itself
+/// failing to build (e.g. an unsupported exec-auth plugin).
+#[derive(Debug)]
+pub enum ClientError {
+    Kubeconfig(KubeconfigError),
+    Client(kube::Error),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Kubeconfig(e) => write!(f, "{}", e),
+            ClientError::Client(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<KubeconfigError> for ClientError {
+    fn from(e: KubeconfigError) -> Self {
+        ClientError::Kubeconfig(e)
+    }
+}
+
+impl From<kube::Error> for ClientError {
+    fn from(e: kube::Error) -> Self {
+        ClientError::Client(e)
+    }
+}
+
+/// Build a `kube::Client` from a kubeconfig file, or from the standard
+/// `KUBECONFIG` resolution (merging colon-separated paths, falling back
+/// to `$HOME/.kube/config`) when no explicit path is given.
+pub async fn client_from_kubeconfig(
+    path: Option<&str>,
+    context: Option<&str>,
+) -> Result<(Client, ClusterInfo), ClientError> {
+    let kubeconfig = match path {
+        Some(path) => Kubeconfig::read_from(Path::new(path))?,
+        None => Kubeconfig::read()?,
+    };
+    let context_name = context
+        .map(String::from)
+        .or_else(|| kubeconfig.current_context.clone())
+        .unwrap_or_else(|| "default".to_string());
+
+    let options = KubeConfigOptions {
+        context: context.map(String::from),
+        ..Default::default()
+    };
+    let config = Config::from_custom_kubeconfig(kubeconfig, &options).await?;
+    let info = ClusterInfo {
+        context: context_name,
+        server: config.cluster_url.to_string(),
+    };
+    let client = Client::try_from(config)?;
+    Ok((client, info))
+}
+
+/// Connectivity check: can we list nodes at all?
+pub async fn check_connectivity(client: Client) -> Result<Vec<Node>, kube::Error> {
+    let nodes: Api<Node> = Api::all(client);
+    let list = nodes.list(&ListParams::default()).await?;
+    Ok(list.items)
+}
+
+/// A node is NotReady unless it has a `Ready` condition whose status is
+/// `True`. Missing status, missing conditions, an empty conditions list,
+/// or a conditions list without a `Ready` entry all count as NotReady.
+fn is_not_ready(node: &Node) -> bool {
+    let ready_condition = node
+        .status
+        .as_ref()
+        .and_then(|status| status.conditions.as_ref())
+        .and_then(|conditions| conditions.iter().find(|c| c.type_ == "Ready"));
+    match ready_condition {
+        Some(condition) => condition.status != "True",
+        None => true,
+    }
+}
+
+/// Returns the nodes whose `Ready` condition is not `True`. A node with
+/// no `Ready` condition at all (missing status, missing conditions, or
+/// an empty conditions list) is treated the same as NotReady.
+pub async fn node_health(client: Client) -> Result<Vec<Node>, kube::Error> {
+    let nodes: Api<Node> = Api::all(client);
+    let list = nodes.list(&ListParams::default()).await?;
+    let not_ready = list.items.into_iter().filter(is_not_ready).collect();
+    Ok(not_ready)
+}
+
+pub async fn events(client: Client, namespace: Option<&str>) -> Result<Vec<Event>, kube::Error> {
+    let events: Api<Event> = match namespace {
+        Some(ns) => Api::namespaced(client, ns),
+        None => Api::all(client),
+    };
+    let list = events.list(&ListParams::default()).await?;
+    Ok(list.items)
+}
+
+pub async fn pod_restarts(client: Client, namespace: Option<&str>) -> Result<Vec<Pod>, kube::Error> {
+    let pods: Api<Pod> = match namespace {
+        Some(ns) => Api::namespaced(client, ns),
+        None => Api::all(client),
+    };
+    let list = pods.list(&ListParams::default()).await?;
+    Ok(list.items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{NodeCondition, NodeStatus};
+
+    fn node_with_conditions(conditions: Option<Vec<NodeCondition>>) -> Node {
+        Node {
+            status: Some(NodeStatus {
+                conditions,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn ready_condition(status: &str) -> NodeCondition {
+        NodeCondition {
+            type_: "Ready".to_string(),
+            status: status.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ready_true_is_not_flagged() {
+        let node = node_with_conditions(Some(vec![ready_condition("True")]));
+        assert!(!is_not_ready(&node));
+    }
+
+    #[test]
+    fn ready_false_is_flagged() {
+        let node = node_with_conditions(Some(vec![ready_condition("False")]));
+        assert!(is_not_ready(&node));
+    }
+
+    #[test]
+    fn missing_ready_condition_is_flagged() {
+        let other = NodeCondition {
+            type_: "DiskPressure".to_string(),
+            status: "False".to_string(),
+            ..Default::default()
+        };
+        let node = node_with_conditions(Some(vec![other]));
+        assert!(is_not_ready(&node));
+    }
+
+    #[test]
+    fn empty_conditions_list_is_flagged() {
+        let node = node_with_conditions(Some(vec![]));
+        assert!(is_not_ready(&node));
+    }
+
+    #[test]
+    fn missing_status_is_flagged() {
+        let node = Node::default();
+        assert!(is_not_ready(&node));
+    }
+}
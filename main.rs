@@ -1,12 +1,97 @@
 extern crate clap;
-use colored::*;
 use clap::{App, Arg};
-use std::env;
-use std::process::{Command, Output};
-use std::io::{self, Write};
+use colored::*;
+use events::{AggregatedEvent, EventLevel};
+use k8s_openapi::api::core::v1::{Node, Pod};
+use resource_pressure::NodePressure;
+use std::str::FromStr;
+use std::time::Instant;
+
+mod events;
+mod exit;
+mod k8s;
+mod resource_pressure;
+mod webhook;
+
+/// Tracks how many checks found a problem or errored, and
+/// whether any error was the kind that means the cluster itself was
+/// unreachable (as opposed to the cluster answering with a problem).
+struct Failures {
+    fail_fast: bool,
+    count: u32,
+    cluster_unreachable: bool,
+}
+
+impl Failures {
+    fn new(fail_fast: bool) -> Self {
+        Failures {
+            fail_fast,
+            count: 0,
+            cluster_unreachable: false,
+        }
+    }
+
+    /// Unwraps the connectivity check's result. A failure here means the
+    /// cluster itself is unreachable, so it gets the dedicated exit code.
+    fn unwrap_connectivity_result<T, E: std::fmt::Display>(
+        &mut self,
+        name: &str,
+        result: Result<T, E>,
+    ) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                eprintln!("{} failed: {}", name, e);
+                self.count += 1;
+                self.cluster_unreachable = true;
+                if self.fail_fast {
+                    std::process::exit(exit::CLUSTER_UNREACHABLE);
+                }
+                None
+            }
+        }
+    }
+
+    /// Unwraps any other check's API result. A failure here means the
+    /// cluster answered but this particular check couldn't complete (e.g.
+    /// an RBAC restriction or a namespaced timeout) — that's a failed
+    /// check, not cluster-unreachable.
+    fn unwrap_api_result<T>(&mut self, name: &str, result: Result<T, kube::Error>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                eprintln!("{} failed: {}", name, e);
+                self.count += 1;
+                if self.fail_fast {
+                    std::process::exit(exit::CHECKS_FAILED);
+                }
+                None
+            }
+        }
+    }
+
+    /// Records whether a successfully-run check found a problem.
+    fn record(&mut self, ok: bool) {
+        if !ok {
+            self.count += 1;
+        }
+    }
 
+    /// The process exit code for everything that happened so far:
+    /// cluster-unreachable takes priority over plain check failures.
+    fn exit_code(&self) -> i32 {
+        if self.cluster_unreachable {
+            exit::CLUSTER_UNREACHABLE
+        } else if self.count > 0 {
+            exit::CHECKS_FAILED
+        } else {
+            exit::OK
+        }
+    }
+}
 
-fn main() {
+#[tokio::main]
+async fn main() {
     let matches = App::new("Suppr")
         .version("0.0.0")
         .author("John Kwiatkoski")
@@ -19,102 +104,305 @@ fn main() {
                 .help("Specify a kubeconfig file to use")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("events-level")
+                .long("events-level")
+                .value_name("LEVEL")
+                .possible_values(&["all", "warning"])
+                .default_value("all")
+                .help("Only report events at this level"),
+        )
+        .arg(
+            Arg::with_name("since")
+                .long("since")
+                .value_name("DURATION")
+                .help("Drop events older than this (e.g. '15m', '1h')")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ping-url")
+                .long("ping-url")
+                .value_name("URL")
+                .help("POST the report to this monitoring/webhook URL")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ping-max-bytes")
+                .long("ping-max-bytes")
+                .value_name("BYTES")
+                .help("Truncate the posted report body to this many bytes")
+                .default_value("10240")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("fail-fast")
+                .long("fail-fast")
+                .help("Abort on the first check that errors, instead of continuing"),
+        )
+        .arg(
+            Arg::with_name("context")
+                .long("context")
+                .value_name("NAME")
+                .help("Kubeconfig context to use instead of the current one")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("namespace")
+                .short("n")
+                .long("namespace")
+                .value_name("NS")
+                .help("Scope pod and event checks to a single namespace instead of all namespaces")
+                .takes_value(true),
+        )
         .get_matches();
 
     let verbose = true;
-    // Gets a value for config if supplied by user, or defaults to "default.conf"
-    let mut kubeconfig = String::from(matches.value_of("kubeconfig").unwrap_or(""));
-    if kubeconfig == "" {
-        let home = env::var("HOME");
-        if home.is_err() {
-            panic!("No kubeconfig provided and HOME environment variable not set");
-        } else {
-            kubeconfig = format!("{}/{}", home.unwrap(), ".kube/config");
-        }
-    }
+    let kubeconfig = matches.value_of("kubeconfig");
     if verbose {
-        println!("Using kubeconfig: {}", kubeconfig);
+        match kubeconfig {
+            Some(path) => println!("Using kubeconfig: {}", path),
+            None => println!("Using kubeconfig: KUBECONFIG env or ~/.kube/config"),
+        }
     }
+
+    let events_level = EventLevel::from_str(matches.value_of("events-level").unwrap_or("all"))
+        .expect("Invalid --events-level");
+    let since = matches
+        .value_of("since")
+        .map(|s| humantime::parse_duration(s).expect("Invalid --since duration"));
+    let namespace = matches.value_of("namespace");
+
+    let mut failures = Failures::new(matches.is_present("fail-fast"));
+
+    let client_result = k8s::client_from_kubeconfig(kubeconfig, matches.value_of("context")).await;
+    let (client, cluster_info) = match failures.unwrap_connectivity_result("Build Kubernetes client", client_result) {
+        Some(value) => value,
+        None => {
+            println!("0 checks passed, {} failed\n", failures.count);
+            std::process::exit(exit::CLUSTER_UNREACHABLE);
+        }
+    };
+
     // Generate a report
     let mut report = String::from("Kubernetes Diagnostic\n");
-    
+    report.push_str(&format!(
+        "Context: {} | Cluster: {}\n",
+        cluster_info.context, cluster_info.server
+    ));
+    let mut timings: Vec<(&str, std::time::Duration)> = Vec::new();
+    let mut checks_run = 0u32;
+
     // check connectivity
-    let api_conn = check_connectivity(&kubeconfig);
-    // No need to check output here just success/failure
-    report.push_str(&format!("Master connectivity check: {}\n", colorize(api_conn.status.success())));
-    
-    let node_health = node_health(&kubeconfig);
-    // Inversing the result as we check for "NotReady", so a failure
-    // is a positive result.
-    report.push_str(&format!("Node health check: {}\n", colorize(!node_health.status.success())));
-    if verbose || node_health.status.success() {
-        report.push_str(&format!("\n{}\n", String::from_utf8(node_health.stdout).expect("Found invalid UTF-8")));
+    let started = Instant::now();
+    let api_conn = k8s::check_connectivity(client.clone()).await;
+    timings.push(("Master connectivity check", started.elapsed()));
+    checks_run += 1;
+    let api_conn = failures.unwrap_connectivity_result("Master connectivity check", api_conn);
+    report.push_str(&format!(
+        "Master connectivity check: {}\n",
+        colorize(api_conn.is_some())
+    ));
+
+    let started = Instant::now();
+    let node_health = k8s::node_health(client.clone()).await;
+    timings.push(("Node health check", started.elapsed()));
+    checks_run += 1;
+    let node_health = failures.unwrap_api_result("Node health check", node_health);
+    // A check is successful when no nodes are unhealthy.
+    let node_health_ok = node_health.as_ref().map(|n| n.is_empty()).unwrap_or(false);
+    if node_health.is_some() {
+        failures.record(node_health_ok);
+    }
+    report.push_str(&format!("Node health check: {}\n", colorize(node_health_ok)));
+    if let Some(node_health) = &node_health {
+        if verbose || !node_health.is_empty() {
+            report.push_str(&format!("\n{}\n", format_node_health(node_health)));
+        }
     }
+
     // check events
-    let events = events(&kubeconfig);
-    report.push_str(&format!("Events: {}\n", colorize(true) ));
-    report.push_str(&format!("Events: {}\n", String::from_utf8(events.stdout).expect("Events output invalid UTF-8")));
+    let started = Instant::now();
+    let raw_events = k8s::events(client.clone(), namespace).await;
+    checks_run += 1;
+    let events = failures
+        .unwrap_api_result("Events", raw_events)
+        .map(|raw| events::filter_and_aggregate(raw, events_level, since));
+    timings.push(("Events", started.elapsed()));
+    report.push_str(&format!("Events: {}\n", colorize(events.is_some())));
+    if let Some(events) = &events {
+        report.push_str(&format!("Events: {}\n", format_events(events)));
+    }
 
     // check pod restarts in kube system
-    let pod_restarts = pod_restarts(&kubeconfig);
-    report.push_str(&format!("Pods: {}\n", colorize(true) ));
-    report.push_str(&format!("{}\n", String::from_utf8(pod_restarts.stdout).expect("Events output invalid UTF-8")));
+    let started = Instant::now();
+    let pod_restarts = k8s::pod_restarts(client.clone(), namespace).await;
+    checks_run += 1;
+    let pod_restarts = failures.unwrap_api_result("Pods", pod_restarts);
+    timings.push(("Pods", started.elapsed()));
+    report.push_str(&format!("Pods: {}\n", colorize(pod_restarts.is_some())));
+    if let Some(pod_restarts) = &pod_restarts {
+        report.push_str(&format!("{}\n", format_pod_restarts(pod_restarts)));
+    }
 
+    // check node resource pressure
+    let started = Instant::now();
+    let node_pressure = resource_pressure::node_pressure(client.clone()).await;
+    checks_run += 1;
+    let node_pressure =
+        failures.unwrap_api_result("Node resource pressure check", node_pressure);
+    timings.push(("Node resource pressure check", started.elapsed()));
+    let pressure_ok = node_pressure
+        .as_ref()
+        .map(|nodes| !nodes.iter().any(|n| n.is_flagged()))
+        .unwrap_or(false);
+    if node_pressure.is_some() {
+        failures.record(pressure_ok);
+    }
+    report.push_str(&format!(
+        "Node resource pressure check: {}\n",
+        colorize(pressure_ok)
+    ));
+    if let Some(node_pressure) = &node_pressure {
+        report.push_str(&format!("\n{}\n", format_node_pressure(node_pressure)));
+    }
+
+    report.push_str("\nCheck timings:\n");
+    for (name, duration) in &timings {
+        report.push_str(&format!("{}: {:.2?}\n", name, duration));
+    }
 
+    let checks_passed = checks_run.saturating_sub(failures.count);
+    report.push_str(&format!(
+        "\n{} checks passed, {} failed\n",
+        checks_passed, failures.count
+    ));
     println!("{}", report);
+
+    if let Some(url) = matches.value_of("ping-url") {
+        let max_bytes = matches
+            .value_of("ping-max-bytes")
+            .unwrap_or("10240")
+            .parse::<usize>()
+            .expect("Invalid --ping-max-bytes");
+        if let Err(e) = webhook::ping(url, &report, failures.count == 0, Some(max_bytes)).await {
+            eprintln!("Failed to ping monitoring URL: {}", e);
+        }
+    }
+
+    std::process::exit(failures.exit_code());
 }
-fn colorize (result: bool) -> String {
+
+fn colorize(result: bool) -> String {
     // Unsure if ✗ or failed is better
     if result {
-        return "✓".green().to_string();
+        "✓".green().to_string()
     } else {
-        return "✘".red().to_string();
+        "✘".red().to_string()
     }
 }
 
 //
-// Kubernetes checks
+// Report formatting
 //
 
-fn check_connectivity(kubeconfig: &str) -> Output {
-    let result = Command::new("kubectl")
-                          .args(&["--kubeconfig", kubeconfig, "get", "nodes"])
-                          .output()
-                          .expect("Master connectivity failed");
-
-    return result;
-
+fn format_node_health(nodes: &[Node]) -> String {
+    nodes
+        .iter()
+        .map(|node| {
+            let name = node.metadata.name.as_deref().unwrap_or("<unknown>");
+            format!("NotReady: {}", name)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-fn node_health(kubeconfig: &str) -> Output {
-    let result = Command::new("kubectl")
-                          .args(&["--kubeconfig", kubeconfig, "get", "nodes", "|", "grep", "NotReady"])
-                          .output()
-                          .expect("Nodes are unhealthy");
+fn format_events(events: &[AggregatedEvent]) -> String {
+    events
+        .iter()
+        .map(|event| {
+            format!(
+                "{}x {} {}: {}",
+                event.count, event.involved_object, event.reason, event.message
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
-    return result;
+fn format_pod_restarts(pods: &[Pod]) -> String {
+    pods.iter()
+        .map(|pod| {
+            let name = pod.metadata.name.as_deref().unwrap_or("<unknown>");
+            let restarts: i32 = pod
+                .status
+                .as_ref()
+                .and_then(|status| status.container_statuses.as_ref())
+                .map(|statuses| statuses.iter().map(|s| s.restart_count).sum())
+                .unwrap_or(0);
+            format!("{}: {} restarts", name, restarts)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-// Json path cant use || for Error or Warning
-// JsonPath can't use 'in' for ["Warning", "Error"]
-// | grep is %^&* here for some reason
-//
-// Just returning all events
-fn events(kubeconfig: &str) -> Output {
-    let result = Command::new("kubectl")
-                            .args(&["--kubeconfig", kubeconfig, "get", "events", "-A" ])
-                            .output()
-                            .expect("Get events failed");
-    //println!("Command: {:?}", result);
-
-    return result;
+fn format_node_pressure(nodes: &[NodePressure]) -> String {
+    nodes
+        .iter()
+        .map(|node| {
+            format!(
+                "{}{}: cpu {:.0}/{:.0} ({:.0}%), mem {:.0}/{:.0} ({:.0}%)",
+                node.name,
+                if node.is_flagged() { " [PRESSURE]" } else { "" },
+                node.cpu_requested,
+                node.cpu_allocatable,
+                node.cpu_ratio() * 100.0,
+                node.mem_requested,
+                node.mem_allocatable,
+                node.mem_ratio() * 100.0,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
-fn pod_restarts(kubeconfig: &str) -> Output {
-    let result = Command::new("kubectl")
-                            .args(&["--kubeconfig", kubeconfig, "get", "pods", "-A"])
-                            .output()
-                            .expect("Get pods failed");
-    //println!("Command: {:?}", result);
-
-    return result;
+
+#[cfg(test)]
+mod failures_tests {
+    use super::*;
+
+    #[test]
+    fn connectivity_failure_marks_cluster_unreachable() {
+        let mut failures = Failures::new(false);
+        failures.unwrap_connectivity_result("Connect", Err::<(), _>("boom"));
+        assert!(failures.cluster_unreachable);
+        assert_eq!(failures.count, 1);
+    }
+
+    #[test]
+    fn api_failure_does_not_mark_cluster_unreachable() {
+        let mut failures = Failures::new(false);
+        failures.unwrap_api_result("Pods", Err::<(), kube::Error>(kube::Error::LinesCodecMaxLineLengthExceeded));
+        assert!(!failures.cluster_unreachable);
+        assert_eq!(failures.count, 1);
+    }
+
+    #[test]
+    fn exit_code_is_ok_when_nothing_failed() {
+        let failures = Failures::new(false);
+        assert_eq!(failures.exit_code(), exit::OK);
+    }
+
+    #[test]
+    fn exit_code_is_checks_failed_when_a_check_found_a_problem() {
+        let mut failures = Failures::new(false);
+        failures.record(false);
+        assert_eq!(failures.exit_code(), exit::CHECKS_FAILED);
+    }
+
+    #[test]
+    fn exit_code_is_cluster_unreachable_even_if_checks_also_failed() {
+        let mut failures = Failures::new(false);
+        failures.record(false);
+        failures.unwrap_connectivity_result("Connect", Err::<(), _>("boom"));
+        assert_eq!(failures.exit_code(), exit::CLUSTER_UNREACHABLE);
+    }
 }
@@ -0,0 +1,178 @@
+// Node resource pressure check: real capacity diagnostics instead of
+// just Ready/NotReady.
+use k8s_openapi::api::core::v1::{Node, Pod};
+use kube::api::{Api, ListParams};
+use kube::Client;
+use kube_quantity::ParsedQuantity;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// Requests exceeding this fraction of allocatable capacity are flagged.
+const REQUEST_RATIO_THRESHOLD: f64 = 0.9;
+
+pub struct NodePressure {
+    pub name: String,
+    pub cpu_requested: f64,
+    pub cpu_allocatable: f64,
+    pub mem_requested: f64,
+    pub mem_allocatable: f64,
+    pub has_pressure_condition: bool,
+}
+
+impl NodePressure {
+    pub fn cpu_ratio(&self) -> f64 {
+        ratio(self.cpu_requested, self.cpu_allocatable)
+    }
+
+    pub fn mem_ratio(&self) -> f64 {
+        ratio(self.mem_requested, self.mem_allocatable)
+    }
+
+    pub fn is_flagged(&self) -> bool {
+        self.has_pressure_condition
+            || self.cpu_ratio() > REQUEST_RATIO_THRESHOLD
+            || self.mem_ratio() > REQUEST_RATIO_THRESHOLD
+    }
+}
+
+fn ratio(requested: f64, allocatable: f64) -> f64 {
+    if allocatable <= 0.0 {
+        0.0
+    } else {
+        requested / allocatable
+    }
+}
+
+fn parse_quantity(s: &str) -> f64 {
+    match ParsedQuantity::try_from(s) {
+        Ok(q) => q.to_bytes_f64().unwrap_or_else(|| {
+            eprintln!("Resource quantity '{}' has no numeric representation", s);
+            0.0
+        }),
+        Err(e) => {
+            eprintln!("Failed to parse resource quantity '{}': {}", s, e);
+            0.0
+        }
+    }
+}
+
+fn has_pressure(node: &Node) -> bool {
+    node.status
+        .as_ref()
+        .and_then(|status| status.conditions.as_ref())
+        .map(|conditions| {
+            conditions.iter().any(|c| {
+                matches!(c.type_.as_str(), "MemoryPressure" | "DiskPressure" | "PIDPressure")
+                    && c.status == "True"
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Fetches nodes and pods, sums requested CPU/memory per node, and
+/// reports how close each node is to its allocatable capacity.
+pub async fn node_pressure(client: Client) -> Result<Vec<NodePressure>, kube::Error> {
+    let nodes: Api<Node> = Api::all(client.clone());
+    let pods: Api<Pod> = Api::all(client);
+
+    let node_list = nodes.list(&ListParams::default()).await?;
+    let pod_list = pods.list(&ListParams::default()).await?;
+
+    let mut cpu_requests: HashMap<String, f64> = HashMap::new();
+    let mut mem_requests: HashMap<String, f64> = HashMap::new();
+
+    for pod in &pod_list.items {
+        // Completed/Failed pods (common leftovers from Jobs/CronJobs) no
+        // longer hold their requested resources against the node.
+        let phase = pod.status.as_ref().and_then(|s| s.phase.as_deref());
+        if matches!(phase, Some("Succeeded") | Some("Failed")) {
+            continue;
+        }
+        let node_name = match pod.spec.as_ref().and_then(|s| s.node_name.clone()) {
+            Some(name) => name,
+            None => continue,
+        };
+        for container in pod.spec.as_ref().map(|s| s.containers.as_slice()).unwrap_or(&[]) {
+            let requests = match container.resources.as_ref().and_then(|r| r.requests.as_ref()) {
+                Some(requests) => requests,
+                None => continue,
+            };
+            if let Some(cpu) = requests.get("cpu") {
+                *cpu_requests.entry(node_name.clone()).or_insert(0.0) += parse_quantity(&cpu.0);
+            }
+            if let Some(mem) = requests.get("memory") {
+                *mem_requests.entry(node_name.clone()).or_insert(0.0) += parse_quantity(&mem.0);
+            }
+        }
+    }
+
+    let pressures = node_list
+        .items
+        .into_iter()
+        .map(|node| {
+            let name = node.metadata.name.clone().unwrap_or_default();
+            let allocatable = node.status.as_ref().and_then(|s| s.allocatable.as_ref());
+            let cpu_allocatable = allocatable
+                .and_then(|a| a.get("cpu"))
+                .map(|q| parse_quantity(&q.0))
+                .unwrap_or(0.0);
+            let mem_allocatable = allocatable
+                .and_then(|a| a.get("memory"))
+                .map(|q| parse_quantity(&q.0))
+                .unwrap_or(0.0);
+
+            NodePressure {
+                cpu_requested: cpu_requests.get(&name).copied().unwrap_or(0.0),
+                mem_requested: mem_requests.get(&name).copied().unwrap_or(0.0),
+                has_pressure_condition: has_pressure(&node),
+                name,
+                cpu_allocatable,
+                mem_allocatable,
+            }
+        })
+        .collect();
+
+    Ok(pressures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pressure(cpu_requested: f64, cpu_allocatable: f64, has_pressure_condition: bool) -> NodePressure {
+        NodePressure {
+            name: "node".to_string(),
+            cpu_requested,
+            cpu_allocatable,
+            mem_requested: 0.0,
+            mem_allocatable: 100.0,
+            has_pressure_condition,
+        }
+    }
+
+    #[test]
+    fn not_flagged_exactly_at_threshold() {
+        let node = pressure(90.0, 100.0, false);
+        assert_eq!(node.cpu_ratio(), 0.9);
+        assert!(!node.is_flagged());
+    }
+
+    #[test]
+    fn flagged_just_over_threshold() {
+        let node = pressure(90.1, 100.0, false);
+        assert!(node.is_flagged());
+    }
+
+    #[test]
+    fn flagged_when_a_pressure_condition_is_true_regardless_of_ratio() {
+        let node = pressure(0.0, 100.0, true);
+        assert!(node.is_flagged());
+    }
+
+    #[test]
+    fn zero_allocatable_does_not_divide_by_zero() {
+        let node = pressure(10.0, 0.0, false);
+        assert_eq!(node.cpu_ratio(), 0.0);
+        assert!(!node.is_flagged());
+    }
+}
@@ -0,0 +1,83 @@
+// Pushes the diagnostic report to a healthchecks.io-style monitoring
+// endpoint so Suppr can be wired into cron/CI rather than only printing
+// to a terminal.
+const DEFAULT_MAX_BYTES: usize = 10 * 1024;
+
+/// Truncate `body` to `max_bytes`, keeping the head and appending an
+/// ellipsis so large event dumps don't blow up the request. The result
+/// never exceeds `max_bytes`: if there's no room for the ellipsis, it's
+/// dropped rather than pushing the total over the cap.
+fn truncate(body: &str, max_bytes: usize) -> String {
+    if body.len() <= max_bytes {
+        return body.to_string();
+    }
+    const ELLIPSIS: &str = "...";
+    if max_bytes <= ELLIPSIS.len() {
+        let mut cut = max_bytes;
+        while cut > 0 && !body.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        return body[..cut].to_string();
+    }
+    let mut cut = max_bytes - ELLIPSIS.len();
+    while cut > 0 && !body.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    format!("{}{}", &body[..cut], ELLIPSIS)
+}
+
+/// POST the report to `url` (or `url/fail` when `success` is false),
+/// healthchecks.io-style, truncated to `max_bytes` (defaults to 10 KB).
+pub async fn ping(
+    url: &str,
+    body: &str,
+    success: bool,
+    max_bytes: Option<usize>,
+) -> Result<(), reqwest::Error> {
+    let target = if success {
+        url.to_string()
+    } else {
+        format!("{}/fail", url.trim_end_matches('/'))
+    };
+    let truncated = truncate(body, max_bytes.unwrap_or(DEFAULT_MAX_BYTES));
+
+    let client = reqwest::Client::new();
+    client.post(&target).body(truncated).send().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_short_bodies_untouched() {
+        assert_eq!(truncate("short report", 1024), "short report");
+    }
+
+    #[test]
+    fn truncates_and_appends_ellipsis() {
+        let body = "a".repeat(20);
+        let truncated = truncate(&body, 10);
+        assert_eq!(truncated, format!("{}...", "a".repeat(7)));
+        assert!(truncated.len() <= 10);
+    }
+
+    #[test]
+    fn truncation_backs_off_to_a_char_boundary() {
+        // Each '✓' is 3 bytes in UTF-8; cutting mid-character would panic
+        // on the byte-slice index, so truncate() must back off to the
+        // nearest valid boundary instead of slicing through one.
+        let body = "✓".repeat(10);
+        let truncated = truncate(&body, 8);
+        assert!(truncated.ends_with("..."));
+    }
+
+    #[test]
+    fn never_exceeds_a_very_small_cap() {
+        let body = "a".repeat(20);
+        let truncated = truncate(&body, 2);
+        assert_eq!(truncated.len(), 2);
+        assert!(!truncated.contains('.'));
+    }
+}